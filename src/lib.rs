@@ -1,12 +1,22 @@
-use crossbeam::channel::{bounded, unbounded, Receiver};
+use crossbeam::channel::{after, bounded, select, tick, unbounded, Receiver};
+use crossbeam::deque::{Injector, Steal};
+use crossbeam::utils::CachePadded;
 use owning_ref::{OwningHandle, OwningRef};
 use primal::{estimate_prime_pi, Primes, Sieve};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 use std::cell::{Cell, RefCell};
 use std::sync::RwLock;
 use std::rc::Rc;
 
+mod sync;
+use sync::{Arc as SyncArc, AtomicUsize, Ordering as AtomicOrdering};
+
 pub fn thread_spawn<'a, T>(
     result: (impl FnOnce() + Send + 'static, T),
 ) -> (thread::JoinHandle<()>, T) {
@@ -124,4 +134,486 @@ pub fn primes_bounded_approx(limit: usize) -> (impl FnOnce() /*+ Send*/, Receive
         WithObj::new(sieve, |s| Sieve::primes_from(s, 0).take(high as usize)),
         high as usize,
     )
+}
+
+/// Scoped-thread counterpart to [`primes_bounded_approx`]: the feeder runs inside a
+/// `crossbeam::thread::scope` and borrows `sieve` instead of boxing it behind the `WithObj`
+/// unsafe lifetime hack. `scope` guarantees the feeder joins before `'env` ends, so `sieve` (or
+/// any other stack-local data the scope closes over) never needs `'static` or a raw-pointer
+/// transmute to be shared with the thread.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// # use primal::Sieve;
+/// let sieve = Sieve::new(100);
+/// crossbeam::thread::scope(|scope| {
+///     let r = primes_bounded_scoped(scope, &sieve, 100);
+///     assert_eq!(r.recv(), Ok(2));
+///     assert_eq!(r.recv(), Ok(3));
+///     assert_eq!(r.recv(), Ok(5));
+/// })
+/// .unwrap();
+/// ```
+pub fn primes_bounded_scoped<'env>(
+    scope: &crossbeam::thread::Scope<'env>,
+    sieve: &'env Sieve,
+    limit: usize,
+) -> Receiver<usize> {
+    let (_, high) = estimate_prime_pi(limit as u64);
+    let (s, r) = bounded::<usize>(high as usize);
+    scope.spawn(move |_| {
+        for p in Sieve::primes_from(sieve, 0).take(high as usize) {
+            if s.send(p).is_err() {
+                return;
+            }
+        }
+    });
+    r
+}
+
+/// Number range covered by one segment of [`primes_in_range`], chosen to keep a segment's
+/// `Vec<bool>` comfortably within L1/L2 cache (128 KiB here).
+const SEGMENT_LEN: usize = 1 << 17;
+
+struct Segment {
+    index: usize,
+    primes: Vec<usize>,
+}
+
+/// Wraps a [`Segment`] so a min-heap can order segments by `index` alone, letting the reorder
+/// buffer below hold a `Segment` without requiring it to implement `Ord` itself.
+struct OrderedSegment(Segment);
+
+impl PartialEq for OrderedSegment {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.index == other.0.index
+    }
+}
+
+impl Eq for OrderedSegment {}
+
+impl PartialOrd for OrderedSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedSegment {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        // Reversed so `BinaryHeap` (a max-heap) pops the lowest `index` first.
+        other.0.index.cmp(&self.0.index)
+    }
+}
+
+/// Parallel segmented sieve of Eratosthenes over `[lo, hi)`, streaming primes in ascending order.
+///
+/// Base primes up to `sqrt(hi)` are computed once with a small [`Sieve`]. `[lo, hi)` is then
+/// split into fixed-size segments ([`SEGMENT_LEN`] wide) whose indices are pushed onto a
+/// `crossbeam::deque::Injector`; `workers` threads steal segments, mark composites in a
+/// segment-local `Vec<bool>` using the base primes (starting at `max(p*p, ceil(start/p) * p)`,
+/// where `start` is the segment's first number), and emit the survivors.
+///
+/// Segments finish out of order, so the returned `Receiver<usize>` is fed by a reorder buffer (a
+/// `BinaryHeap` keyed on segment index): segment 0 must be flushed before segment 1 is, segment 1
+/// before segment 2, and so on, which is what keeps the stream globally ascending even though the
+/// workers race each other.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// let r = primes_in_range(100, 150, 4);
+/// let primes: Vec<usize> = r.iter().collect();
+/// assert_eq!(primes, vec![101, 103, 107, 109, 113, 127, 131, 137, 139, 149]);
+/// ```
+pub fn primes_in_range(lo: usize, hi: usize, workers: usize) -> Receiver<usize> {
+    let base_limit = (hi as f64).sqrt() as usize + 1;
+    let base_sieve = Sieve::new(base_limit);
+    let base_primes: Arc<Vec<usize>> = Arc::new(
+        Sieve::primes_from(&base_sieve, 0)
+            .take_while(|&p| p.saturating_mul(p) < hi)
+            .collect(),
+    );
+
+    let segment_count = hi.saturating_sub(lo).div_ceil(SEGMENT_LEN);
+    let injector = Arc::new(Injector::new());
+    for index in 0..segment_count {
+        injector.push(index);
+    }
+
+    let (seg_tx, seg_rx) = unbounded::<Segment>();
+    for _ in 0..workers.max(1) {
+        let injector = Arc::clone(&injector);
+        let base_primes = Arc::clone(&base_primes);
+        let seg_tx = seg_tx.clone();
+        thread::spawn(move || loop {
+            let index = match injector.steal() {
+                Steal::Success(index) => index,
+                Steal::Empty => break,
+                Steal::Retry => continue,
+            };
+            let start = lo + index * SEGMENT_LEN;
+            let end = (start + SEGMENT_LEN).min(hi);
+            let mut composite = vec![false; end - start];
+            for &p in base_primes.iter() {
+                let first = std::cmp::max(p * p, start.div_ceil(p) * p);
+                let mut m = first;
+                while m < end {
+                    composite[m - start] = true;
+                    m += p;
+                }
+            }
+            let primes = (start..end)
+                .zip(composite.iter())
+                .filter(|&(n, &is_composite)| n >= 2 && !is_composite)
+                .map(|(n, _)| n)
+                .collect();
+            if seg_tx.send(Segment { index, primes }).is_err() {
+                return;
+            }
+        });
+    }
+    drop(seg_tx);
+
+    let (out_tx, out_rx) = unbounded::<usize>();
+    thread::spawn(move || {
+        let mut pending: BinaryHeap<OrderedSegment> = BinaryHeap::new();
+        let mut next = 0usize;
+        for segment in seg_rx {
+            pending.push(OrderedSegment(segment));
+            while let Some(OrderedSegment(segment)) = pending.peek() {
+                if segment.index != next {
+                    break;
+                }
+                let OrderedSegment(segment) = pending.pop().unwrap();
+                for p in segment.primes {
+                    if out_tx.send(p).is_err() {
+                        return;
+                    }
+                }
+                next += 1;
+            }
+        }
+    });
+    out_rx
+}
+
+/// Streams primes, same as [`primes_unbounded`], but gives up the feeder thread once `timeout`
+/// elapses instead of running forever. The feeder `select!`s on every iteration between sending
+/// the next prime and a one-shot `after(timeout)` channel, so a blocking `recv` on the consumer
+/// side gets `Err(RecvError)` promptly at the deadline rather than only when the receiver itself
+/// is dropped.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// # use std::time::Duration;
+/// let (thread, r) = thread_spawn(primes_until_deadline(Duration::from_secs(60)));
+/// assert_eq!(r.recv(), Ok(2));
+/// assert_eq!(r.recv(), Ok(3));
+/// drop(r);
+/// thread.join();
+/// ```
+pub fn primes_until_deadline(timeout: Duration) -> (impl FnOnce() + Send, Receiver<usize>) {
+    // Zero capacity, so no backlog of primes is left for the consumer to drain past the deadline.
+    let (s, r) = bounded::<usize>(0);
+    (
+        move || {
+            let deadline = after(timeout);
+            for p in Primes::all() {
+                select! {
+                    send(s, p) -> res => {
+                        if res.is_err() {
+                            return;
+                        }
+                    }
+                    recv(deadline) -> _ => return,
+                }
+            }
+        },
+        r,
+    )
+}
+
+/// Paces [`primes_unbounded`]'s feeder to at most one send per `interval`, using
+/// crossbeam-channel's `tick` flavor. Handy for demos and rate-limited pipelines that would
+/// otherwise drown in primes.
+pub fn primes_throttled(interval: Duration) -> (impl FnOnce() + Send, Receiver<usize>) {
+    let (s, r) = unbounded::<usize>();
+    (
+        move || {
+            let ticker = tick(interval);
+            for p in Primes::all() {
+                if ticker.recv().is_err() {
+                    return;
+                }
+                if s.send(p).is_err() {
+                    return;
+                }
+            }
+        },
+        r,
+    )
+}
+
+/// Shared feeder loop for the batched entry points below: accumulates `batch` primes into a
+/// `Vec` before each `send`, trading latency for far less per-prime synchronization overhead than
+/// sending one `usize` at a time. `progress`, if given, is bumped once per batch.
+fn batched_feeder<I: Iterator<Item = usize>>(
+    it: I,
+    batch: usize,
+    s: crossbeam::channel::Sender<Vec<usize>>,
+    progress: Option<SyncArc<CachePadded<AtomicUsize>>>,
+) {
+    let mut buf = Vec::with_capacity(batch);
+    for p in it {
+        buf.push(p);
+        if buf.len() == batch {
+            if let Some(progress) = &progress {
+                progress.fetch_add(buf.len(), AtomicOrdering::Relaxed);
+            }
+            if s.send(mem::replace(&mut buf, Vec::with_capacity(batch))).is_err() {
+                return;
+            }
+        }
+    }
+    if !buf.is_empty() {
+        if let Some(progress) = &progress {
+            progress.fetch_add(buf.len(), AtomicOrdering::Relaxed);
+        }
+        let _ = s.send(buf);
+    }
+}
+
+/// `primes_bounded_approx`, but batched: the feeder accumulates `batch` primes into a `Vec`
+/// before each `send`, trading a little latency for much less per-prime channel overhead on
+/// dense streams. Like [`primes_bounded_scoped`], the feeder runs inside a
+/// `crossbeam::thread::scope` and borrows `sieve` instead of boxing it behind the `WithObj`
+/// unsafe lifetime hack.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// # use primal::Sieve;
+/// let sieve = Sieve::new(20);
+/// crossbeam::thread::scope(|scope| {
+///     let r = primes_bounded_batched(scope, &sieve, 20, 3);
+///     assert_eq!(r.recv(), Ok(vec![2, 3, 5]));
+///     assert_eq!(r.recv(), Ok(vec![7, 11, 13]));
+///     assert_eq!(r.recv(), Ok(vec![17, 19]));
+/// })
+/// .unwrap();
+/// ```
+pub fn primes_bounded_batched<'env>(
+    scope: &crossbeam::thread::Scope<'env>,
+    sieve: &'env Sieve,
+    limit: usize,
+    batch: usize,
+) -> Receiver<Vec<usize>> {
+    let (_, high) = estimate_prime_pi(limit as u64);
+    let (s, r) = bounded::<Vec<usize>>(high as usize / batch.max(1) + 1);
+    scope.spawn(move |_| {
+        batched_feeder(Sieve::primes_from(sieve, 0).take(high as usize), batch, s, None)
+    });
+    r
+}
+
+/// Unbounded counterpart to [`primes_bounded_batched`]: batches [`primes_unbounded`]'s stream
+/// instead of a fixed-size sieve's.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// let (thread, r) = thread_spawn(primes_unbounded_batched(3));
+/// assert_eq!(r.recv(), Ok(vec![2, 3, 5]));
+/// assert_eq!(r.recv(), Ok(vec![7, 11, 13]));
+/// drop(r);
+/// thread.join();
+/// ```
+pub fn primes_unbounded_batched(batch: usize) -> (impl FnOnce() + Send, Receiver<Vec<usize>>) {
+    let (s, r) = unbounded::<Vec<usize>>();
+    (move || batched_feeder(Primes::all(), batch, s, None), r)
+}
+
+/// Adds a shared progress counter to [`primes_bounded_batched`]: the feeder bumps it once per
+/// batch, as a `CachePadded<AtomicUsize>` so a monitoring thread can poll how many primes have
+/// been produced so far without false-sharing the producer's hot cache line. Like
+/// [`primes_bounded_batched`], the feeder runs inside a `crossbeam::thread::scope` and borrows
+/// `sieve` rather than going through the `WithObj` unsafe lifetime hack.
+///
+/// ```
+/// # use primal_crossbeam::*;
+/// # use primal::Sieve;
+/// use std::sync::atomic::Ordering;
+/// let sieve = Sieve::new(20);
+/// crossbeam::thread::scope(|scope| {
+///     let (r, progress) = primes_bounded_batched_with_progress(scope, &sieve, 20, 3);
+///     assert_eq!(r.recv(), Ok(vec![2, 3, 5]));
+///     assert!(progress.load(Ordering::Relaxed) >= 3);
+/// })
+/// .unwrap();
+/// ```
+pub fn primes_bounded_batched_with_progress<'env>(
+    scope: &crossbeam::thread::Scope<'env>,
+    sieve: &'env Sieve,
+    limit: usize,
+    batch: usize,
+) -> (Receiver<Vec<usize>>, SyncArc<CachePadded<AtomicUsize>>) {
+    let (_, high) = estimate_prime_pi(limit as u64);
+    let (s, r) = bounded::<Vec<usize>>(high as usize / batch.max(1) + 1);
+    let progress = SyncArc::new(CachePadded::new(AtomicUsize::new(0)));
+    let feeder_progress = SyncArc::clone(&progress);
+    scope.spawn(move |_| {
+        batched_feeder(
+            Sieve::primes_from(sieve, 0).take(high as usize),
+            batch,
+            s,
+            Some(feeder_progress),
+        )
+    });
+    (r, progress)
+}
+
+/// Namespace for a resumable, memory-proportional prime stream: where [`primes_bounded_approx`]
+/// commits to one `Sieve` sized for a fixed limit up front, [`PrimeStream::spawn`]'s feeder starts
+/// with a small `Sieve` and, once it has emitted every prime below the current frontier, doubles
+/// the frontier and re-sieves `0..frontier` to pick up the next batch. The result is an
+/// effectively unbounded stream without either [`primes_unbounded`]'s always-growing wheel
+/// iterator or one large fixed-size `Sieve` allocated up front.
+pub struct PrimeStream;
+
+impl PrimeStream {
+    /// Frontier `PrimeStream` starts sieving from before its first doubling.
+    const INITIAL_LIMIT: usize = 1 << 10;
+
+    /// Spawns the feeder thread and returns its handle alongside the `Receiver` it feeds.
+    ///
+    /// ```
+    /// # use primal_crossbeam::*;
+    /// let (thread, r) = PrimeStream::spawn();
+    /// assert_eq!(r.recv(), Ok(2));
+    /// assert_eq!(r.recv(), Ok(3));
+    /// assert_eq!(r.recv(), Ok(5));
+    /// drop(r);
+    /// thread.join();
+    /// ```
+    pub fn spawn() -> (thread::JoinHandle<()>, Receiver<usize>) {
+        let (s, r) = unbounded::<usize>();
+        thread_spawn((
+            move || {
+                // Tracks how far this thread has already sieved and emitted; `Cell` is enough
+                // since the frontier is only ever touched by this one feeder thread.
+                let frontier = Cell::new(Self::INITIAL_LIMIT);
+                let mut emitted = 0usize;
+                loop {
+                    let limit = frontier.get();
+                    let sieve = Sieve::new(limit);
+                    let mut count = 0usize;
+                    for p in Sieve::primes_from(&sieve, 0) {
+                        count += 1;
+                        if count <= emitted {
+                            continue;
+                        }
+                        if s.send(p).is_err() {
+                            return;
+                        }
+                    }
+                    emitted = count;
+                    frontier.set(limit * 2);
+                }
+            },
+            r,
+        ))
+    }
+}
+
+/// Model-checked tests for the crate's shared-state paths. These live inside the `lib` target
+/// (rather than a separate `tests/loom.rs` binary) and are invoked with `RUSTFLAGS="--cfg loom"
+/// cargo test --release --lib --features loom`, since loom explores thread interleavings instead
+/// of running the test body once.
+///
+/// Both tests drive [`batched_feeder`] itself (rather than a standalone atomic toy) through an
+/// **unbounded** channel: an unbounded `send` never parks, so it's safe to call from a modeled
+/// `loom_thread` even though crossbeam-channel's internals aren't loom-aware. A blocking `recv`
+/// is never issued while threads are still being modeled, only after every spawned thread has
+/// joined.
+#[cfg(all(loom, test))]
+mod loom_tests {
+    use super::sync::{Arc, AtomicUsize, Ordering};
+    use crossbeam::channel::unbounded;
+    use crossbeam::utils::CachePadded;
+    use loom::thread as loom_thread;
+
+    /// Two feeders bumping the same progress counter, as two concurrent callers of
+    /// [`batched_feeder`] would, must never lose an update no matter how loom interleaves the two
+    /// `fetch_add`s. Uses the crate's own `sync::{Arc, AtomicUsize}` so loom can actually see and
+    /// schedule the shared accesses, and feeds a real `unbounded` channel (the flavor
+    /// `primes_unbounded_batched` uses) so the feeder's `send` calls are real channel sends.
+    #[test]
+    fn batched_feeder_progress_counter_never_loses_updates() {
+        loom::model(|| {
+            let counter = Arc::new(CachePadded::new(AtomicUsize::new(0)));
+            let (s1, r) = unbounded::<Vec<usize>>();
+            let s2 = s1.clone();
+            let c1 = Arc::clone(&counter);
+            let c2 = Arc::clone(&counter);
+            let t1 = loom_thread::spawn(move || {
+                super::batched_feeder(vec![2usize, 3, 5].into_iter(), 3, s1, Some(c1));
+            });
+            let t2 = loom_thread::spawn(move || {
+                super::batched_feeder(vec![7usize, 11, 13].into_iter(), 3, s2, Some(c2));
+            });
+            t1.join().unwrap();
+            t2.join().unwrap();
+
+            assert_eq!(counter.load(Ordering::Relaxed), 6);
+            let mut batches: Vec<Vec<usize>> = r.try_iter().collect();
+            batches.sort();
+            assert_eq!(batches, vec![vec![2, 3, 5], vec![7, 11, 13]]);
+        });
+    }
+
+    /// [`batched_feeder`] must stop cleanly, rather than panic or hang, if its receiver is
+    /// dropped concurrently instead of after it finishes — the race `primes_bounded_batched`'s
+    /// caller hits if it drops the `Receiver` while the feeder thread is still running. Loom
+    /// explores every interleaving of the drop against the feeder's `send` calls.
+    #[test]
+    fn batched_feeder_stops_cleanly_when_receiver_dropped() {
+        loom::model(|| {
+            let (s, r) = unbounded::<Vec<usize>>();
+            let feeder = loom_thread::spawn(move || {
+                super::batched_feeder(0..10, 2, s, None);
+            });
+            let dropper = loom_thread::spawn(move || {
+                drop(r);
+            });
+            feeder.join().unwrap();
+            dropper.join().unwrap();
+        });
+    }
+}
+
+/// Plain unit tests with no concurrency, run by ordinary `cargo test` (unlike [`loom_tests`],
+/// which only builds under `--cfg loom`).
+#[cfg(test)]
+mod tests {
+    use super::{OrderedSegment, Segment};
+
+    /// The [`OrderedSegment`] wrapper that `primes_in_range`'s single merge thread feeds into its
+    /// `BinaryHeap` reorder buffer must flush segments in index order regardless of the order
+    /// `push` was called in. There's no concurrency here — the heap is only ever touched by that
+    /// one thread — so this needs no loom machinery.
+    #[test]
+    fn reorder_buffer_is_permutation_safe() {
+        let segments = vec![
+            Segment { index: 2, primes: vec![5] },
+            Segment { index: 0, primes: vec![2] },
+            Segment { index: 1, primes: vec![3] },
+        ];
+        let mut heap: std::collections::BinaryHeap<OrderedSegment> =
+            std::collections::BinaryHeap::new();
+        for segment in segments {
+            heap.push(OrderedSegment(segment));
+        }
+        let mut flushed = Vec::new();
+        while let Some(OrderedSegment(segment)) = heap.pop() {
+            flushed.push(segment.index);
+        }
+        assert_eq!(flushed, vec![0, 1, 2]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,24 @@
+//! Indirection over atomic types so the shared-state path (the batched progress counter) can be
+//! exercised by `loom`'s model checker, and can otherwise run on targets lacking native 64-bit
+//! atomics via the optional `portable-atomic` dependency.
+//!
+//! The progress-counter code reaches for `crate::sync::{Arc, AtomicUsize, Ordering}` instead of
+//! `std::sync`/`core::sync::atomic` directly, as the `concurrent-queue` crate does.
+//! `primes_in_range` still shares its work queue and base primes through `std::sync::Arc`.
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::Arc;
+
+#[cfg(all(not(loom), feature = "portable-atomic"))]
+pub(crate) use portable_atomic::AtomicUsize;
+#[cfg(all(not(loom), feature = "portable-atomic"))]
+pub(crate) use std::sync::atomic::Ordering;
+#[cfg(all(not(loom), feature = "portable-atomic"))]
+pub(crate) use std::sync::Arc;
+
+#[cfg(all(not(loom), not(feature = "portable-atomic")))]
+pub(crate) use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(not(loom), not(feature = "portable-atomic")))]
+pub(crate) use std::sync::Arc;